@@ -3,11 +3,11 @@ use super::event_handle::EventListenerHandle;
 use super::media_query_handle::MediaQueryListHandle;
 use crate::dpi::{LogicalPosition, PhysicalPosition, PhysicalSize};
 use crate::error::OsError as RootOE;
-use crate::event::{Force, MouseButton, MouseScrollDelta};
+use crate::event::{Force, Ime, MouseButton, MouseScrollDelta};
 use crate::keyboard::{Key, KeyCode, KeyLocation, ModifiersState};
 use crate::platform_impl::{OsError, PlatformSpecificWindowBuilderAttributes};
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use js_sys::Promise;
@@ -17,12 +17,68 @@ use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
     AddEventListenerOptions, Event, FocusEvent, HtmlCanvasElement, KeyboardEvent,
-    MediaQueryListEvent, MouseEvent, WheelEvent,
+    MediaQueryListEvent, WheelEvent,
 };
 
+mod ime_handler;
 mod mouse_handler;
 mod pointer_handler;
 
+/// Per-category policy controlling whether a canvas's DOM listeners call
+/// `stop_propagation`/`cancel_bubble` and `prevent_default` on the events they observe.
+///
+/// Canvases used to stop propagation of every event unconditionally, which made it impossible to
+/// embed one in a page that also wants to see (or handle) the same gesture, e.g. page-level
+/// scrolling or keyboard shortcuts. Each category can now be configured independently via
+/// [`Canvas::set_event_propagation_policy`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EventPropagationPolicy {
+    pub keyboard: CategoryPropagationPolicy,
+    pub wheel: CategoryPropagationPolicy,
+    pub pointer: CategoryPropagationPolicy,
+    pub touch: CategoryPropagationPolicy,
+    pub focus: CategoryPropagationPolicy,
+}
+
+impl EventPropagationPolicy {
+    fn for_category(&self, category: EventCategory) -> CategoryPropagationPolicy {
+        match category {
+            EventCategory::Keyboard => self.keyboard,
+            EventCategory::Wheel => self.wheel,
+            EventCategory::Pointer => self.pointer,
+            EventCategory::Touch => self.touch,
+            EventCategory::Focus => self.focus,
+        }
+    }
+}
+
+/// Whether a handler should call `stop_propagation`/`cancel_bubble` and `prevent_default` on the
+/// events it observes. Defaults to stopping propagation, as canvases always did, and not
+/// preventing default.
+#[derive(Clone, Copy, Debug)]
+pub struct CategoryPropagationPolicy {
+    pub stop_propagation: bool,
+    pub prevent_default: bool,
+}
+
+impl Default for CategoryPropagationPolicy {
+    fn default() -> Self {
+        Self {
+            stop_propagation: true,
+            prevent_default: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EventCategory {
+    Keyboard,
+    Wheel,
+    Pointer,
+    Touch,
+    Focus,
+}
+
 #[allow(dead_code)]
 pub struct Canvas {
     common: Common,
@@ -35,13 +91,33 @@ pub struct Canvas {
     on_mouse_wheel: Option<EventListenerHandle<dyn FnMut(WheelEvent)>>,
     on_fullscreen_change: Option<EventListenerHandle<dyn FnMut(Event)>>,
     on_dark_mode: Option<MediaQueryListHandle>,
+    // Capture-phase listeners on `window` that drain a queued fullscreen request on the next
+    // transient activation, wherever on the page it happens to land.
+    on_window_fullscreen_pointerup: Option<EventListenerHandle<dyn FnMut(Event)>>,
+    on_window_fullscreen_keyup: Option<EventListenerHandle<dyn FnMut(Event)>>,
+    on_window_fullscreen_click: Option<EventListenerHandle<dyn FnMut(Event)>>,
+    // Run once, from `Drop`, after every DOM listener has been torn down. This is how the event
+    // loop finds out to emit `WindowEvent::Destroyed` for this canvas.
+    on_destroy: Option<Box<dyn FnMut()>>,
     mouse_state: MouseState,
+    ime: Option<ime_handler::ImeHandler>,
 }
 
 struct Common {
     /// Note: resizing the HTMLCanvasElement should go through `backend::set_canvas_size` to ensure the DPI factor is maintained.
     raw: HtmlCanvasElement,
     wants_fullscreen: Rc<RefCell<bool>>,
+    // Set whenever we proactively call the browser's `requestFullscreen`, so the next
+    // `fullscreenchange` can tell "we asked for this" apart from an externally-triggered one.
+    requested_fullscreen: Rc<Cell<bool>>,
+    propagation_policy: Rc<Cell<EventPropagationPolicy>>,
+    // Set for the duration of an IME composition, so the keydown/keyup paths can suppress the
+    // `key_text` they'd otherwise report redundantly alongside the IME's own preedit/commit text.
+    composing: Rc<Cell<bool>>,
+    // Set for the duration of the IME subsystem's own `focus()`/`blur()` calls as it redirects
+    // real DOM focus between the canvas and its hidden input, so `Canvas::on_blur` can tell the
+    // canvas blur this causes apart from a real loss of focus and avoid reporting it.
+    redirecting_focus: Rc<Cell<bool>>,
 }
 
 impl Canvas {
@@ -80,11 +156,26 @@ impl Canvas {
             MouseState::NoPointerEvent(mouse_handler::MouseHandler::new())
         };
 
+        let common = Common {
+            raw: canvas,
+            wants_fullscreen: Rc::new(RefCell::new(false)),
+            requested_fullscreen: Rc::new(Cell::new(false)),
+            propagation_policy: Rc::new(Cell::new(EventPropagationPolicy::default())),
+            composing: Rc::new(Cell::new(false)),
+            redirecting_focus: Rc::new(Cell::new(false)),
+        };
+
         Ok(Canvas {
-            common: Common {
-                raw: canvas,
-                wants_fullscreen: Rc::new(RefCell::new(false)),
-            },
+            on_window_fullscreen_pointerup: Some(
+                common.add_window_transient_activation_listener("pointerup"),
+            ),
+            on_window_fullscreen_keyup: Some(
+                common.add_window_transient_activation_listener("keyup"),
+            ),
+            on_window_fullscreen_click: Some(
+                common.add_window_transient_activation_listener("click"),
+            ),
+            common,
             on_touch_start: None,
             on_touch_end: None,
             on_blur: None,
@@ -94,7 +185,9 @@ impl Canvas {
             on_mouse_wheel: None,
             on_fullscreen_change: None,
             on_dark_mode: None,
+            on_destroy: None,
             mouse_state,
+            ime: None,
         })
     }
 
@@ -139,56 +232,82 @@ impl Canvas {
         &self.common.raw
     }
 
-    pub fn on_touch_start(&mut self, prevent_default: bool) {
-        self.on_touch_start = Some(self.common.add_event("touchstart", move |event: Event| {
-            if prevent_default {
-                event.prevent_default();
-            }
-        }));
+    /// Sets the per-category event propagation/prevent-default policy. Listeners read it live on
+    /// each event, so this also affects ones already registered.
+    pub fn set_event_propagation_policy(&mut self, policy: EventPropagationPolicy) {
+        self.common.propagation_policy.set(policy);
     }
 
-    pub fn on_touch_end(&mut self, prevent_default: bool) {
-        self.on_touch_end = Some(self.common.add_event("touchend", move |event: Event| {
-            if prevent_default {
-                event.prevent_default();
-            }
-        }));
+    pub fn on_touch_start(&mut self) {
+        self.on_touch_start = Some(self.common.add_event(
+            "touchstart",
+            EventCategory::Touch,
+            move |_: Event| {},
+        ));
+    }
+
+    pub fn on_touch_end(&mut self) {
+        self.on_touch_end = Some(self.common.add_event(
+            "touchend",
+            EventCategory::Touch,
+            move |_: Event| {},
+        ));
     }
 
     pub fn on_blur<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(),
     {
-        self.on_blur = Some(self.common.add_event("blur", move |_: FocusEvent| {
-            handler();
-        }));
+        let redirecting_focus = self.common.redirecting_focus.clone();
+        self.on_blur = Some(self.common.add_event(
+            "blur",
+            EventCategory::Focus,
+            move |_: FocusEvent| {
+                // The IME subsystem blurs the canvas itself when it redirects focus to its
+                // hidden input; that's not a real loss of focus, so don't report it as one.
+                if redirecting_focus.get() {
+                    return;
+                }
+                handler();
+            },
+        ));
     }
 
     pub fn on_focus<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(),
     {
-        self.on_focus = Some(self.common.add_event("focus", move |_: FocusEvent| {
-            handler();
-        }));
+        self.on_focus = Some(self.common.add_event(
+            "focus",
+            EventCategory::Focus,
+            move |_: FocusEvent| {
+                handler();
+            },
+        ));
     }
 
-    pub fn on_keyboard_release<F>(&mut self, mut handler: F, prevent_default: bool)
+    pub fn on_keyboard_release<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(KeyCode, Key, Option<SmolStr>, KeyLocation, bool, ModifiersState),
     {
+        let composing = self.common.composing.clone();
         self.on_keyboard_release = Some(self.common.add_user_event(
             "keyup",
+            EventCategory::Keyboard,
             move |event: KeyboardEvent| {
-                if prevent_default {
-                    event.prevent_default();
-                }
                 let key = event::key(&event);
                 let modifiers = event::keyboard_modifiers(&event);
+                // While composing, the IME delivers its own preedit/commit text; reporting
+                // `key_text` here too would duplicate it.
+                let key_text = if composing.get() {
+                    None
+                } else {
+                    event::key_text(&event)
+                };
                 handler(
                     event::key_code(&event),
                     key,
-                    event::key_text(&event),
+                    key_text,
                     event::key_location(&event),
                     event.repeat(),
                     modifiers,
@@ -197,22 +316,26 @@ impl Canvas {
         ));
     }
 
-    pub fn on_keyboard_press<F>(&mut self, mut handler: F, prevent_default: bool)
+    pub fn on_keyboard_press<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(KeyCode, Key, Option<SmolStr>, KeyLocation, bool, ModifiersState),
     {
+        let composing = self.common.composing.clone();
         self.on_keyboard_press = Some(self.common.add_user_event(
             "keydown",
+            EventCategory::Keyboard,
             move |event: KeyboardEvent| {
-                if prevent_default {
-                    event.prevent_default();
-                }
                 let key = event::key(&event);
                 let modifiers = event::keyboard_modifiers(&event);
+                let key_text = if composing.get() {
+                    None
+                } else {
+                    event::key_text(&event)
+                };
                 handler(
                     event::key_code(&event),
                     key,
-                    event::key_text(&event),
+                    key_text,
                     event::key_location(&event),
                     event.repeat(),
                     modifiers,
@@ -267,18 +390,14 @@ impl Canvas {
         }
     }
 
-    pub fn on_cursor_move<M, T>(
-        &mut self,
-        mouse_handler: M,
-        touch_handler: T,
-        prevent_default: bool,
-    ) where
+    pub fn on_cursor_move<M, T>(&mut self, mouse_handler: M, touch_handler: T)
+    where
         M: 'static + FnMut(i32, PhysicalPosition<f64>, PhysicalPosition<f64>, ModifiersState),
         T: 'static + FnMut(i32, PhysicalPosition<f64>, Force),
     {
         match &mut self.mouse_state {
             MouseState::HasPointerEvent(h) => {
-                h.on_cursor_move(&self.common, mouse_handler, touch_handler, prevent_default)
+                h.on_cursor_move(&self.common, mouse_handler, touch_handler)
             }
             MouseState::NoPointerEvent(h) => h.on_cursor_move(&self.common, mouse_handler),
         }
@@ -293,30 +412,61 @@ impl Canvas {
         }
     }
 
-    pub fn on_mouse_wheel<F>(&mut self, mut handler: F, prevent_default: bool)
+    /// Tilt of the stylus along the x/y axes, in degrees, as of the last pen pointer event.
+    /// `(0.0, 0.0)` when no pen has been used or pointer events aren't supported.
+    pub fn stylus_tilt(&self) -> (f64, f64) {
+        match &self.mouse_state {
+            MouseState::HasPointerEvent(h) => (h.tilt_x(), h.tilt_y()),
+            MouseState::NoPointerEvent(_) => (0.0, 0.0),
+        }
+    }
+
+    /// Normalized pressure (0..=1) of the stylus as of the last pen pointer event. `0.0` when no
+    /// pen has been used or pointer events aren't supported.
+    pub fn stylus_pressure(&self) -> f64 {
+        match &self.mouse_state {
+            MouseState::HasPointerEvent(h) => h.pressure(),
+            MouseState::NoPointerEvent(_) => 0.0,
+        }
+    }
+
+    pub fn on_mouse_wheel<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(i32, MouseScrollDelta, ModifiersState),
     {
-        self.on_mouse_wheel = Some(self.common.add_event("wheel", move |event: WheelEvent| {
-            if prevent_default {
-                event.prevent_default();
-            }
-
-            if let Some(delta) = event::mouse_scroll_delta(&event) {
-                let modifiers = event::mouse_modifiers(&event);
-                handler(0, delta, modifiers);
-            }
-        }));
+        self.on_mouse_wheel = Some(self.common.add_event(
+            "wheel",
+            EventCategory::Wheel,
+            move |event: WheelEvent| {
+                if let Some(delta) = event::mouse_scroll_delta(&event) {
+                    let modifiers = event::mouse_modifiers(&event);
+                    handler(0, delta, modifiers);
+                }
+            },
+        ));
     }
 
     pub fn on_fullscreen_change<F>(&mut self, mut handler: F)
     where
         F: 'static + FnMut(),
     {
-        self.on_fullscreen_change = Some(
-            self.common
-                .add_event("fullscreenchange", move |_: Event| handler()),
-        );
+        let wants_fullscreen = self.common.wants_fullscreen.clone();
+        let requested_fullscreen = self.common.requested_fullscreen.clone();
+
+        self.on_fullscreen_change = Some(self.common.add_event(
+            "fullscreenchange",
+            EventCategory::Focus,
+            move |_: Event| {
+                if !requested_fullscreen.replace(false) {
+                    // Fullscreen was entered (or exited) by something other than a request we
+                    // issued, so a queued request is now stale: drop it instead of firing it (and
+                    // double-triggering a fullscreenchange) on the next transient activation.
+                    *wants_fullscreen.borrow_mut() = false;
+                }
+
+                handler();
+            },
+        ));
     }
 
     pub fn on_dark_mode<F>(&mut self, mut handler: F)
@@ -331,6 +481,40 @@ impl Canvas {
         self.on_dark_mode = MediaQueryListHandle::new("(prefers-color-scheme: dark)", closure);
     }
 
+    /// Registers a handler run once all of this canvas's DOM listeners have been torn down,
+    /// either because the window was dropped or the event loop exited. The canvas element itself
+    /// is left in the DOM; this is purely for the event loop to emit `WindowEvent::Destroyed`.
+    pub fn on_destroy<F>(&mut self, handler: F)
+    where
+        F: 'static + FnMut(),
+    {
+        self.on_destroy = Some(Box::new(handler));
+    }
+
+    /// Registers a handler for IME composition events: [`Ime::Enabled`] when composition starts,
+    /// [`Ime::Preedit`] as the in-progress text changes, and [`Ime::Commit`] once it (or an
+    /// uncomposed insertion, e.g. predictive text) is finalized. Lazily creates the hidden
+    /// text-input surface the IME attaches to on first call.
+    pub fn on_ime<F>(&mut self, handler: F)
+    where
+        F: 'static + FnMut(Ime),
+    {
+        self.ime
+            .get_or_insert_with(|| {
+                ime_handler::ImeHandler::new(&self.common, self.common.composing.clone())
+            })
+            .on_ime(handler);
+    }
+
+    /// Positions the IME candidate window near `position` (a logical offset from the canvas's
+    /// top-left corner), e.g. to track a text caret. A no-op until [`Canvas::on_ime`] has been
+    /// called at least once.
+    pub fn set_ime_position(&self, position: LogicalPosition<f64>) {
+        if let Some(ime) = &self.ime {
+            ime.set_ime_position(&self.common, position);
+        }
+    }
+
     pub fn request_fullscreen(&self) {
         self.common.request_fullscreen()
     }
@@ -340,6 +524,8 @@ impl Canvas {
     }
 
     pub fn remove_listeners(&mut self) {
+        self.on_touch_start = None;
+        self.on_touch_end = None;
         self.on_focus = None;
         self.on_blur = None;
         self.on_keyboard_release = None;
@@ -347,6 +533,10 @@ impl Canvas {
         self.on_mouse_wheel = None;
         self.on_fullscreen_change = None;
         self.on_dark_mode = None;
+        self.on_window_fullscreen_pointerup = None;
+        self.on_window_fullscreen_keyup = None;
+        self.on_window_fullscreen_click = None;
+        self.ime = None;
         match &mut self.mouse_state {
             MouseState::HasPointerEvent(h) => h.remove_listeners(),
             MouseState::NoPointerEvent(h) => h.remove_listeners(),
@@ -354,21 +544,43 @@ impl Canvas {
     }
 }
 
+impl Drop for Canvas {
+    fn drop(&mut self) {
+        // Tear down every DOM listener (and the closures they keep alive) before letting the
+        // event loop know the window is gone. The `<canvas>` element itself is left in the DOM;
+        // removing it is the embedder's responsibility.
+        self.remove_listeners();
+
+        if let Some(on_destroy) = &mut self.on_destroy {
+            on_destroy();
+        }
+    }
+}
+
 impl Common {
     fn add_event<E, F>(
         &self,
         event_name: &'static str,
+        category: EventCategory,
         mut handler: F,
     ) -> EventListenerHandle<dyn FnMut(E)>
     where
         E: 'static + AsRef<web_sys::Event> + wasm_bindgen::convert::FromWasmAbi,
         F: 'static + FnMut(E),
     {
+        let propagation_policy = self.propagation_policy.clone();
+
         let closure = Closure::wrap(Box::new(move |event: E| {
             {
+                let policy = propagation_policy.get().for_category(category);
                 let event_ref = event.as_ref();
-                event_ref.stop_propagation();
-                event_ref.cancel_bubble();
+                if policy.stop_propagation {
+                    event_ref.stop_propagation();
+                    event_ref.cancel_bubble();
+                }
+                if policy.prevent_default {
+                    event_ref.prevent_default();
+                }
             }
 
             handler(event);
@@ -383,6 +595,7 @@ impl Common {
     fn add_user_event<E, F>(
         &self,
         event_name: &'static str,
+        category: EventCategory,
         mut handler: F,
     ) -> EventListenerHandle<dyn FnMut(E)>
     where
@@ -390,12 +603,14 @@ impl Common {
         F: 'static + FnMut(E),
     {
         let wants_fullscreen = self.wants_fullscreen.clone();
+        let requested_fullscreen = self.requested_fullscreen.clone();
         let canvas = self.raw.clone();
 
-        self.add_event(event_name, move |event: E| {
+        self.add_event(event_name, category, move |event: E| {
             handler(event);
 
             if *wants_fullscreen.borrow() {
+                requested_fullscreen.set(true);
                 canvas
                     .request_fullscreen()
                     .expect("Failed to enter fullscreen");
@@ -404,41 +619,61 @@ impl Common {
         })
     }
 
-    // This function is used exclusively for mouse events (not pointer events).
-    // Due to the need for mouse capturing, the mouse event handlers are added
-    // to the window instead of the canvas element, which requires special
-    // handling to control event propagation.
-    fn add_window_mouse_event<F>(
+    // Registers a capture-phase listener on `window` (not the canvas) for a transient-activation
+    // event category, so a queued fullscreen request can be fulfilled by a gesture anywhere on
+    // the page, not only ones that land on the canvas itself.
+    fn add_window_transient_activation_listener(
         &self,
         event_name: &'static str,
-        mut handler: F,
-    ) -> EventListenerHandle<dyn FnMut(MouseEvent)>
-    where
-        F: 'static + FnMut(MouseEvent),
-    {
+    ) -> EventListenerHandle<dyn FnMut(Event)> {
         let wants_fullscreen = self.wants_fullscreen.clone();
+        let requested_fullscreen = self.requested_fullscreen.clone();
         let canvas = self.raw.clone();
         let window = web_sys::window().expect("Failed to obtain window");
 
-        let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
-            handler(event);
-
+        let closure = Closure::wrap(Box::new(move |_: Event| {
             if *wants_fullscreen.borrow() {
-                canvas
-                    .request_fullscreen()
-                    .expect("Failed to enter fullscreen");
-                *wants_fullscreen.borrow_mut() = false;
+                requested_fullscreen.set(true);
+
+                #[wasm_bindgen]
+                extern "C" {
+                    type ElementExt;
+
+                    #[wasm_bindgen(catch, method, js_name = requestFullscreen)]
+                    fn request_fullscreen(this: &ElementExt) -> Result<JsValue, JsValue>;
+                }
+
+                let raw: &ElementExt = canvas.unchecked_ref();
+
+                // Same graceful fallback as `Common::request_fullscreen`: unlike a gesture that
+                // landed on the canvas itself, one landing anywhere on the page may still be
+                // rejected (no transient activation, an iframe missing `allow="fullscreen"`, a
+                // Permissions-Policy denial, ...), so queue a retry instead of panicking the whole
+                // app over an unrelated page click.
+                match raw.request_fullscreen() {
+                    Ok(value) if !value.is_undefined() => {
+                        let promise: Promise = value.unchecked_into();
+                        let wants_fullscreen = wants_fullscreen.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            if JsFuture::from(promise).await.is_err() {
+                                *wants_fullscreen.borrow_mut() = true
+                            }
+                        });
+                        *wants_fullscreen.borrow_mut() = false;
+                    }
+                    // Rejected synchronously, or we're on Safari v<16.4 (no `Promise` returned):
+                    // leave it queued for the next transient activation.
+                    _ => *wants_fullscreen.borrow_mut() = true,
+                }
             }
         }) as Box<dyn FnMut(_)>);
 
-        let listener = EventListenerHandle::with_options(
+        EventListenerHandle::with_options(
             &window,
             event_name,
             closure,
             AddEventListenerOptions::new().capture(true),
-        );
-
-        listener
+        )
     }
 
     pub fn request_fullscreen(&self) {
@@ -452,6 +687,8 @@ impl Common {
 
         let raw: &ElementExt = self.raw.unchecked_ref();
 
+        self.requested_fullscreen.set(true);
+
         // This should return a `Promise`, but Safari v<16.4 is not up-to-date with the spec.
         match raw.request_fullscreen() {
             Ok(value) if !value.is_undefined() => {
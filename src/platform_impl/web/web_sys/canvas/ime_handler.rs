@@ -0,0 +1,216 @@
+use super::super::event_handle::EventListenerHandle;
+use super::Common;
+use crate::dpi::LogicalPosition;
+use crate::event::Ime;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{CompositionEvent, Event, FocusEvent, HtmlInputElement, InputEvent};
+
+// A hidden, focus-synced text input overlaid on the canvas. The canvas itself has no text
+// content for a native IME to attach a composition session to, or for the OS candidate window to
+// anchor near, so we give it one: a 1x1px, fully transparent `<input>` that tracks the canvas's
+// focus state and is never shown to the user.
+pub(super) struct ImeHandler {
+    input: HtmlInputElement,
+    composing: Rc<Cell<bool>>,
+    // Shared with `Common`: set for the duration of our own `input.focus()`/`input.blur()` calls
+    // from `sync_focus`, so the blur that call provokes on the *other* element (moving focus away
+    // from the canvas blurs it, and vice versa) doesn't re-trigger the mirroring and fight itself,
+    // and so `Canvas::on_blur` can tell our redirect apart from a real loss of focus.
+    redirecting_focus: Rc<Cell<bool>>,
+    on_composition_start: Option<EventListenerHandle<dyn FnMut(CompositionEvent)>>,
+    on_composition_update: Option<EventListenerHandle<dyn FnMut(CompositionEvent)>>,
+    on_composition_end: Option<EventListenerHandle<dyn FnMut(CompositionEvent)>>,
+    on_before_input: Option<EventListenerHandle<dyn FnMut(InputEvent)>>,
+    on_canvas_focus: Option<EventListenerHandle<dyn FnMut(FocusEvent)>>,
+    on_canvas_blur: Option<EventListenerHandle<dyn FnMut(FocusEvent)>>,
+    // Once real DOM focus has been redirected to `input`, a later loss of focus fires `blur` on
+    // `input`, not on the canvas. Forward a genuine one back onto the canvas so `Canvas::on_blur`
+    // (which only listens there) still finds out.
+    on_input_blur: Option<EventListenerHandle<dyn FnMut(FocusEvent)>>,
+}
+
+impl ImeHandler {
+    pub fn new(common: &Common, composing: Rc<Cell<bool>>) -> Self {
+        let document = common
+            .raw
+            .owner_document()
+            .expect("canvas has no owner document");
+
+        let input: HtmlInputElement = document
+            .create_element("input")
+            .expect("Failed to create IME input element")
+            .unchecked_into();
+        let _ = input.set_attribute("type", "text");
+        let _ = input.set_attribute("tabindex", "-1");
+        let _ = input.set_attribute("autocomplete", "off");
+        let _ = input.set_attribute(
+            "style",
+            "position: absolute; width: 1px; height: 1px; opacity: 0; border: none; \
+             padding: 0; margin: 0; pointer-events: none;",
+        );
+
+        if let Some(parent) = common.raw.parent_node() {
+            let _ = parent.insert_before(&input, common.raw.next_sibling().as_ref());
+        }
+
+        let mut handler = Self {
+            input,
+            composing,
+            redirecting_focus: common.redirecting_focus.clone(),
+            on_composition_start: None,
+            on_composition_update: None,
+            on_composition_end: None,
+            on_before_input: None,
+            on_canvas_focus: None,
+            on_canvas_blur: None,
+            on_input_blur: None,
+        };
+        handler.sync_focus(common);
+        handler
+    }
+
+    pub fn on_ime<F>(&mut self, mut handler: F)
+    where
+        F: 'static + FnMut(Ime),
+    {
+        let handler = Rc::new(RefCell::new(move |ime| handler(ime)));
+
+        let composing = self.composing.clone();
+        let on_start = handler.clone();
+        let closure = Closure::wrap(Box::new(move |_: CompositionEvent| {
+            composing.set(true);
+            (on_start.borrow_mut())(Ime::Enabled);
+        }) as Box<dyn FnMut(_)>);
+        self.on_composition_start = Some(EventListenerHandle::new(
+            &self.input,
+            "compositionstart",
+            closure,
+        ));
+
+        let on_update = handler.clone();
+        let closure = Closure::wrap(Box::new(move |event: CompositionEvent| {
+            let preedit = event.data().unwrap_or_default();
+            let cursor = preedit.chars().count();
+            (on_update.borrow_mut())(Ime::Preedit(preedit, Some((cursor, cursor))));
+        }) as Box<dyn FnMut(_)>);
+        self.on_composition_update = Some(EventListenerHandle::new(
+            &self.input,
+            "compositionupdate",
+            closure,
+        ));
+
+        let composing = self.composing.clone();
+        let input = self.input.clone();
+        let on_end = handler.clone();
+        let closure = Closure::wrap(Box::new(move |event: CompositionEvent| {
+            composing.set(false);
+            input.set_value("");
+            (on_end.borrow_mut())(Ime::Commit(event.data().unwrap_or_default()));
+        }) as Box<dyn FnMut(_)>);
+        self.on_composition_end = Some(EventListenerHandle::new(
+            &self.input,
+            "compositionend",
+            closure,
+        ));
+
+        // Covers commits that don't go through a composition session at all, e.g. predictive
+        // text or emoji picker insertions on mobile.
+        let composing = self.composing.clone();
+        let input = self.input.clone();
+        let closure = Closure::wrap(Box::new(move |event: InputEvent| {
+            if composing.get() {
+                return;
+            }
+            if let Some(data) = event.data() {
+                event.prevent_default();
+                input.set_value("");
+                (handler.borrow_mut())(Ime::Commit(data));
+            }
+        }) as Box<dyn FnMut(_)>);
+        self.on_before_input = Some(EventListenerHandle::new(
+            &self.input,
+            "beforeinput",
+            closure,
+        ));
+    }
+
+    // Keeps the hidden input's focus in lockstep with the canvas's, since only a focused element
+    // can host a composition session.
+    //
+    // Focusing the input synchronously blurs the canvas, which would otherwise immediately
+    // re-enter this same mirroring and blur the input right back. `redirecting_focus` marks the
+    // span of our own `focus`/`blur` calls so the mirrored handler can tell that apart from a
+    // real blur of the canvas and skip reacting to it.
+    fn sync_focus(&mut self, common: &Common) {
+        let input = self.input.clone();
+        let redirecting_focus = self.redirecting_focus.clone();
+        let closure = Closure::wrap(Box::new(move |_: FocusEvent| {
+            redirecting_focus.set(true);
+            let _ = input.focus();
+            redirecting_focus.set(false);
+        }) as Box<dyn FnMut(_)>);
+        self.on_canvas_focus = Some(EventListenerHandle::new(&common.raw, "focus", closure));
+
+        let input = self.input.clone();
+        let redirecting_focus = self.redirecting_focus.clone();
+        let closure = Closure::wrap(Box::new(move |_: FocusEvent| {
+            if redirecting_focus.get() {
+                return;
+            }
+            redirecting_focus.set(true);
+            let _ = input.blur();
+            redirecting_focus.set(false);
+        }) as Box<dyn FnMut(_)>);
+        self.on_canvas_blur = Some(EventListenerHandle::new(&common.raw, "blur", closure));
+
+        // Once focus has been redirected to `input`, the canvas never sees a `blur` of its own
+        // again on a real loss of focus; re-dispatch one on the canvas so `Canvas::on_blur`,
+        // which only listens there, still reports it.
+        let canvas = common.raw.clone();
+        let redirecting_focus = self.redirecting_focus.clone();
+        let closure = Closure::wrap(Box::new(move |_: FocusEvent| {
+            if redirecting_focus.get() {
+                return;
+            }
+            if let Ok(event) = Event::new("blur") {
+                let _ = canvas.dispatch_event(&event);
+            }
+        }) as Box<dyn FnMut(_)>);
+        self.on_input_blur = Some(EventListenerHandle::new(&self.input, "blur", closure));
+    }
+
+    pub fn set_ime_position(&self, common: &Common, position: LogicalPosition<f64>) {
+        let bounds = common.raw.get_bounding_client_rect();
+        let _ = self
+            .input
+            .style()
+            .set_property("left", &format!("{}px", bounds.x() + position.x));
+        let _ = self
+            .input
+            .style()
+            .set_property("top", &format!("{}px", bounds.y() + position.y));
+    }
+
+    pub fn remove_listeners(&mut self) {
+        self.on_composition_start = None;
+        self.on_composition_update = None;
+        self.on_composition_end = None;
+        self.on_before_input = None;
+        self.on_canvas_focus = None;
+        self.on_canvas_blur = None;
+        self.on_input_blur = None;
+    }
+}
+
+impl Drop for ImeHandler {
+    fn drop(&mut self) {
+        self.remove_listeners();
+        if let Some(parent) = self.input.parent_node() {
+            let _ = parent.remove_child(&self.input);
+        }
+    }
+}
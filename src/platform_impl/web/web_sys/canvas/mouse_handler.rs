@@ -0,0 +1,118 @@
+use super::super::event;
+use super::super::event_handle::EventListenerHandle;
+use super::{Common, EventCategory};
+use crate::dpi::PhysicalPosition;
+use crate::event::{ModifiersState, MouseButton};
+
+use web_sys::MouseEvent;
+
+// Patch for Safari, which as of this writing doesn't support `PointerEvent`. This handler uses
+// plain old `MouseEvent` and therefore doesn't support multi-touch or pen pressure, but it keeps
+// the web backend usable on browsers lacking the Pointer Events spec.
+#[allow(dead_code)]
+pub(super) struct MouseHandler {
+    on_cursor_leave: Option<EventListenerHandle<dyn FnMut(MouseEvent)>>,
+    on_cursor_enter: Option<EventListenerHandle<dyn FnMut(MouseEvent)>>,
+    on_mouse_press: Option<EventListenerHandle<dyn FnMut(MouseEvent)>>,
+    on_mouse_release: Option<EventListenerHandle<dyn FnMut(MouseEvent)>>,
+    on_mouse_move: Option<EventListenerHandle<dyn FnMut(MouseEvent)>>,
+}
+
+impl MouseHandler {
+    pub fn new() -> Self {
+        Self {
+            on_cursor_leave: None,
+            on_cursor_enter: None,
+            on_mouse_press: None,
+            on_mouse_release: None,
+            on_mouse_move: None,
+        }
+    }
+
+    pub fn on_cursor_leave<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, ModifiersState),
+    {
+        self.on_cursor_leave = Some(common.add_event(
+            "mouseout",
+            EventCategory::Pointer,
+            move |event: MouseEvent| {
+                handler(0, event::mouse_modifiers(&event));
+            },
+        ));
+    }
+
+    pub fn on_cursor_enter<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, ModifiersState),
+    {
+        self.on_cursor_enter = Some(common.add_event(
+            "mouseover",
+            EventCategory::Pointer,
+            move |event: MouseEvent| {
+                handler(0, event::mouse_modifiers(&event));
+            },
+        ));
+    }
+
+    pub fn on_mouse_release<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, MouseButton, ModifiersState),
+    {
+        self.on_mouse_release = Some(common.add_event(
+            "mouseup",
+            EventCategory::Pointer,
+            move |event: MouseEvent| {
+                handler(
+                    0,
+                    event::mouse_button(&event),
+                    event::mouse_modifiers(&event),
+                );
+            },
+        ));
+    }
+
+    pub fn on_mouse_press<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, PhysicalPosition<f64>, MouseButton, ModifiersState),
+    {
+        self.on_mouse_press = Some(common.add_event(
+            "mousedown",
+            EventCategory::Pointer,
+            move |event: MouseEvent| {
+                handler(
+                    0,
+                    event::mouse_position(&event),
+                    event::mouse_button(&event),
+                    event::mouse_modifiers(&event),
+                );
+            },
+        ));
+    }
+
+    pub fn on_cursor_move<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, PhysicalPosition<f64>, PhysicalPosition<f64>, ModifiersState),
+    {
+        self.on_mouse_move = Some(common.add_event(
+            "mousemove",
+            EventCategory::Pointer,
+            move |event: MouseEvent| {
+                handler(
+                    0,
+                    event::mouse_position(&event),
+                    event::mouse_delta(&event),
+                    event::mouse_modifiers(&event),
+                );
+            },
+        ));
+    }
+
+    pub fn remove_listeners(&mut self) {
+        self.on_cursor_leave = None;
+        self.on_cursor_enter = None;
+        self.on_mouse_press = None;
+        self.on_mouse_release = None;
+        self.on_mouse_move = None;
+    }
+}
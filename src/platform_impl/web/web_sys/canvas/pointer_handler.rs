@@ -0,0 +1,225 @@
+use super::super::event;
+use super::super::event_handle::EventListenerHandle;
+use super::{Common, EventCategory};
+use crate::dpi::PhysicalPosition;
+use crate::event::{Force, ModifiersState, MouseButton};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use web_sys::PointerEvent;
+
+#[allow(dead_code)]
+pub(super) struct PointerHandler {
+    on_cursor_leave: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    on_cursor_enter: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    on_pointer_press: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    on_pointer_release: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    on_pointer_move: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    on_pointer_cancel: Option<EventListenerHandle<dyn FnMut(PointerEvent)>>,
+    // Updated on every pen pointer event so `tilt_x`/`tilt_y`/`pressure` can be queried by callers
+    // that don't otherwise have a hook into the raw `PointerEvent` (e.g. on press/release), since
+    // pen events are routed through the mouse callbacks rather than the touch/`Force` channel.
+    stylus_state: Rc<Cell<(f64, f64, f64)>>,
+}
+
+impl PointerHandler {
+    pub fn new() -> Self {
+        Self {
+            on_cursor_leave: None,
+            on_cursor_enter: None,
+            on_pointer_press: None,
+            on_pointer_release: None,
+            on_pointer_move: None,
+            on_pointer_cancel: None,
+            stylus_state: Rc::new(Cell::new((0.0, 0.0, 0.0))),
+        }
+    }
+
+    /// Tilt of the stylus along the x axis, in degrees, as of the last pen pointer event.
+    pub fn tilt_x(&self) -> f64 {
+        self.stylus_state.get().0
+    }
+
+    /// Tilt of the stylus along the y axis, in degrees, as of the last pen pointer event.
+    pub fn tilt_y(&self) -> f64 {
+        self.stylus_state.get().1
+    }
+
+    /// Normalized pressure (0..=1) of the stylus as of the last pen pointer event.
+    pub fn pressure(&self) -> f64 {
+        self.stylus_state.get().2
+    }
+
+    pub fn on_cursor_leave<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, ModifiersState),
+    {
+        self.on_cursor_leave = Some(common.add_event(
+            "pointerleave",
+            EventCategory::Pointer,
+            move |event: PointerEvent| {
+                handler(event.pointer_id(), event::mouse_modifiers(&event));
+            },
+        ));
+    }
+
+    pub fn on_cursor_enter<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, ModifiersState),
+    {
+        self.on_cursor_enter = Some(common.add_event(
+            "pointerenter",
+            EventCategory::Pointer,
+            move |event: PointerEvent| {
+                handler(event.pointer_id(), event::mouse_modifiers(&event));
+            },
+        ));
+    }
+
+    // `touch_handler` carries the `Force` reported for real touch contacts, since `MouseButton`
+    // has no notion of pressure. Pen events are routed entirely through `mouse_handler`, with
+    // their pressure/tilt available separately via `pressure()`/`tilt_x()`/`tilt_y()`.
+    pub fn on_mouse_release<M, T>(
+        &mut self,
+        common: &Common,
+        mut mouse_handler: M,
+        mut touch_handler: T,
+    ) where
+        M: 'static + FnMut(i32, MouseButton, ModifiersState),
+        T: 'static + FnMut(i32, PhysicalPosition<f64>, Force),
+    {
+        let stylus_state = self.stylus_state.clone();
+        self.on_pointer_release = Some(common.add_event(
+            "pointerup",
+            EventCategory::Pointer,
+            move |event: PointerEvent| match event.pointer_type().as_str() {
+                "mouse" | "pen" => {
+                    if event.pointer_type() == "pen" {
+                        stylus_state.set((
+                            event.tilt_x() as f64,
+                            event.tilt_y() as f64,
+                            event.pressure() as f64,
+                        ));
+                    }
+                    mouse_handler(
+                        event.pointer_id(),
+                        event::mouse_button(&event),
+                        event::mouse_modifiers(&event),
+                    );
+                }
+                _ => touch_handler(
+                    event.pointer_id(),
+                    event::mouse_position(&event),
+                    touch_force(&event),
+                ),
+            },
+        ));
+    }
+
+    pub fn on_mouse_press<M, T>(
+        &mut self,
+        common: &Common,
+        mut mouse_handler: M,
+        mut touch_handler: T,
+    ) where
+        M: 'static + FnMut(i32, PhysicalPosition<f64>, MouseButton, ModifiersState),
+        T: 'static + FnMut(i32, PhysicalPosition<f64>, Force),
+    {
+        let stylus_state = self.stylus_state.clone();
+        self.on_pointer_press = Some(common.add_event(
+            "pointerdown",
+            EventCategory::Pointer,
+            move |event: PointerEvent| match event.pointer_type().as_str() {
+                "mouse" | "pen" => {
+                    if event.pointer_type() == "pen" {
+                        stylus_state.set((
+                            event.tilt_x() as f64,
+                            event.tilt_y() as f64,
+                            event.pressure() as f64,
+                        ));
+                    }
+                    mouse_handler(
+                        event.pointer_id(),
+                        event::mouse_position(&event),
+                        event::mouse_button(&event),
+                        event::mouse_modifiers(&event),
+                    );
+                }
+                _ => touch_handler(
+                    event.pointer_id(),
+                    event::mouse_position(&event),
+                    touch_force(&event),
+                ),
+            },
+        ));
+    }
+
+    pub fn on_cursor_move<M, T>(
+        &mut self,
+        common: &Common,
+        mut mouse_handler: M,
+        mut touch_handler: T,
+    ) where
+        M: 'static + FnMut(i32, PhysicalPosition<f64>, PhysicalPosition<f64>, ModifiersState),
+        T: 'static + FnMut(i32, PhysicalPosition<f64>, Force),
+    {
+        let stylus_state = self.stylus_state.clone();
+        self.on_pointer_move = Some(common.add_event(
+            "pointermove",
+            EventCategory::Pointer,
+            move |event: PointerEvent| match event.pointer_type().as_str() {
+                "mouse" | "pen" => {
+                    if event.pointer_type() == "pen" {
+                        stylus_state.set((
+                            event.tilt_x() as f64,
+                            event.tilt_y() as f64,
+                            event.pressure() as f64,
+                        ));
+                    }
+                    mouse_handler(
+                        event.pointer_id(),
+                        event::mouse_position(&event),
+                        event::mouse_delta(&event),
+                        event::mouse_modifiers(&event),
+                    );
+                }
+                _ => touch_handler(
+                    event.pointer_id(),
+                    event::mouse_position(&event),
+                    touch_force(&event),
+                ),
+            },
+        ));
+    }
+
+    pub fn on_touch_cancel<F>(&mut self, common: &Common, mut handler: F)
+    where
+        F: 'static + FnMut(i32, PhysicalPosition<f64>, Force),
+    {
+        self.on_pointer_cancel = Some(common.add_event(
+            "pointercancel",
+            EventCategory::Touch,
+            move |event: PointerEvent| {
+                handler(
+                    event.pointer_id(),
+                    event::mouse_position(&event),
+                    touch_force(&event),
+                );
+            },
+        ));
+    }
+
+    pub fn remove_listeners(&mut self) {
+        self.on_cursor_leave = None;
+        self.on_cursor_enter = None;
+        self.on_pointer_press = None;
+        self.on_pointer_release = None;
+        self.on_pointer_move = None;
+        self.on_pointer_cancel = None;
+    }
+}
+
+fn touch_force(event: &PointerEvent) -> Force {
+    Force::Normalized(event.pressure() as f64)
+}